@@ -0,0 +1,203 @@
+// infix operator grammars (arithmetic, boolean logic, ...) can't be
+// expressed as fixed nesting the way `pair`/`left`/`right` can: precedence
+// and associativity have to decide, at each operator, how far to the
+// right the current sub-expression extends. This module implements that
+// via precedence climbing rather than bolting on left-recursion.
+
+use crate::{any_char, one_or_more, pred, BoxedParser, ParseError, Parser};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+// one entry in the precedence table: a parser that recognizes the
+// operator and produces a tag identifying which one matched, plus how
+// tightly it binds and which way it associates
+struct Operator<'a, Op> {
+    parser: BoxedParser<'a, &'a str, Op, ParseError<'a>>,
+    binding_power: u8,
+    associativity: Associativity,
+}
+
+impl<'a, Op> Operator<'a, Op> {
+    fn new<P>(parser: P, binding_power: u8, associativity: Associativity) -> Self
+    where
+        P: Parser<&'a str, Op, ParseError<'a>> + 'a,
+    {
+        Operator {
+            parser: BoxedParser::new(parser),
+            binding_power,
+            associativity,
+        }
+    }
+}
+
+// parse one atom, then repeatedly look for an operator whose left binding
+// power clears `min_bp`; recurse with a higher (left-associative) or equal
+// (right-associative) minimum bound to parse its right-hand side, folding
+// the result into `lhs` before looping for the next operator
+fn parse_expr<'a, A, Op>(
+    input: &'a str,
+    min_bp: u8,
+    atom: &dyn Parser<&'a str, A, ParseError<'a>>,
+    operators: &[Operator<'a, Op>],
+    fold: &dyn Fn(A, Op, A) -> Result<A, &'static str>,
+) -> Result<(&'a str, A), ParseError<'a>>
+where
+    Op: Copy,
+{
+    let (mut rest, mut lhs) = atom.parse(input)?;
+
+    loop {
+        let matched = operators
+            .iter()
+            .find_map(|op| op.parser.parse(rest).ok().map(|(next, tag)| (op, next, tag)));
+
+        let (op, next_rest, tag) = match matched {
+            Some(found) if found.0.binding_power >= min_bp => found,
+            _ => break,
+        };
+
+        let right_bp = match op.associativity {
+            Associativity::Left => op.binding_power + 1,
+            Associativity::Right => op.binding_power,
+        };
+
+        let (after_rhs, rhs) = parse_expr(next_rest, right_bp, atom, operators, fold)?;
+        lhs = fold(lhs, tag, rhs).map_err(|expected| ParseError::new(after_rhs, expected))?;
+        rest = after_rhs;
+    }
+
+    Ok((rest, lhs))
+}
+
+// builder: an atom parser plus a precedence table becomes a `Parser<A>`
+// usable anywhere the other combinators are — including recursively,
+// inside the atom parser itself, for a fully-parenthesized sub-expression
+fn expression<'a, A, Op, Atom, Fold>(
+    atom: Atom,
+    operators: Vec<Operator<'a, Op>>,
+    fold: Fold,
+) -> impl Parser<&'a str, A, ParseError<'a>>
+where
+    Op: Copy + 'a,
+    Atom: Parser<&'a str, A, ParseError<'a>> + 'a,
+    Fold: Fn(A, Op, A) -> Result<A, &'static str> + 'a,
+{
+    move |input| parse_expr(input, 0, &atom, &operators, &fold)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+// the digit run `one_or_more` matched has no length limit, so it can spell
+// out a value past `i64::MAX` - that's a parse failure, not a panic, so it
+// surfaces through `and_then` as a `ParseError` rather than an `unwrap`
+fn parse_digits<'a>(digits: String) -> impl Parser<&'a str, i64, ParseError<'a>> {
+    move |input: &'a str| match digits.parse::<i64>() {
+        Ok(value) => Ok((input, value)),
+        Err(_) => Err(ParseError::new(input, "integer literal that fits in i64")),
+    }
+}
+
+fn number<'a>() -> impl Parser<&'a str, i64, ParseError<'a>> {
+    one_or_more(pred(any_char, |c: &char| c.is_ascii_digit()))
+        .map(|digits| digits.into_iter().collect::<String>())
+        .and_then(parse_digits)
+}
+
+fn arithmetic_operators<'a>() -> Vec<Operator<'a, ArithmeticOp>> {
+    use crate::match_literal;
+
+    vec![
+        Operator::new(match_literal("+").map(|_| ArithmeticOp::Add), 1, Associativity::Left),
+        Operator::new(match_literal("-").map(|_| ArithmeticOp::Sub), 1, Associativity::Left),
+        Operator::new(match_literal("*").map(|_| ArithmeticOp::Mul), 2, Associativity::Left),
+        Operator::new(match_literal("/").map(|_| ArithmeticOp::Div), 2, Associativity::Left),
+        Operator::new(match_literal("^").map(|_| ArithmeticOp::Pow), 3, Associativity::Right),
+    ]
+}
+
+// every branch here can fail on well-formed input (overflow, or a zero
+// divisor) - that's a parse failure, not a panic, same reasoning as
+// `parse_digits` above
+fn fold_arithmetic(lhs: i64, op: ArithmeticOp, rhs: i64) -> Result<i64, &'static str> {
+    match op {
+        ArithmeticOp::Add => lhs.checked_add(rhs).ok_or("addition that fits in i64"),
+        ArithmeticOp::Sub => lhs.checked_sub(rhs).ok_or("subtraction that fits in i64"),
+        ArithmeticOp::Mul => lhs.checked_mul(rhs).ok_or("multiplication that fits in i64"),
+        ArithmeticOp::Div => lhs.checked_div(rhs).ok_or("division by a nonzero value"),
+        ArithmeticOp::Pow => rhs
+            .try_into()
+            .ok()
+            .and_then(|exp: u32| lhs.checked_pow(exp))
+            .ok_or("exponentiation that fits in i64"),
+    }
+}
+
+#[test]
+fn expression_combinator_respects_precedence() {
+    let parser = expression(number(), arithmetic_operators(), fold_arithmetic);
+    assert_eq!(Ok(("", 14)), parser.parse("2+3*4"));
+    assert_eq!(Ok(("", 20)), parser.parse("2*3+4*3+2"));
+}
+
+#[test]
+fn number_reports_overflow_instead_of_panicking() {
+    // a digit run this long is valid per the grammar even though it
+    // overflows i64 - that has to come back as a parse error, not a panic
+    assert!(number().parse("99999999999999999999").is_err());
+}
+
+#[test]
+fn fold_arithmetic_reports_overflow_instead_of_panicking() {
+    let parser = expression(number(), arithmetic_operators(), fold_arithmetic);
+    assert!(parser.parse("2^100").is_err());
+    assert!(parser.parse("9223372036854775807+1").is_err());
+}
+
+#[test]
+fn fold_arithmetic_reports_division_by_zero_instead_of_panicking() {
+    let parser = expression(number(), arithmetic_operators(), fold_arithmetic);
+    assert!(parser.parse("1/0").is_err());
+}
+
+#[test]
+fn expression_combinator_respects_associativity() {
+    // `-` is left-associative: (10-3)-2, not 10-(3-2)
+    let left_assoc = expression(number(), arithmetic_operators(), fold_arithmetic);
+    assert_eq!(Ok(("", 5)), left_assoc.parse("10-3-2"));
+
+    // `^` is right-associative: 2^(3^2) = 2^9, not (2^3)^2
+    let right_assoc = expression(number(), arithmetic_operators(), fold_arithmetic);
+    assert_eq!(Ok(("", 512)), right_assoc.parse("2^3^2"));
+}
+
+#[test]
+fn expression_combinator_recurses_through_parentheses() {
+    use crate::{left, match_literal, right};
+
+    // the atom itself recurses into a fully-parenthesized `expr`, which is
+    // exactly the case fixed-nesting combinators can't express
+    fn atom<'a>() -> BoxedParser<'a, &'a str, i64, ParseError<'a>> {
+        BoxedParser::new(move |input: &'a str| {
+            let parenthesized = right(match_literal("("), left(expr(), match_literal(")")));
+            parenthesized.parse(input).or_else(|_| number().parse(input))
+        })
+    }
+
+    fn expr<'a>() -> impl Parser<&'a str, i64, ParseError<'a>> {
+        expression(atom(), arithmetic_operators(), fold_arithmetic)
+    }
+
+    assert_eq!(Ok(("", 20)), expr().parse("(2+3)*4"));
+    assert_eq!(Ok(("", 14)), expr().parse("2+(3*4)"));
+}
@@ -1,3 +1,6 @@
+mod expression;
+mod tree;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct Element {
     name: String,
@@ -5,24 +8,222 @@ struct Element {
     children: Vec<Element>,
 }
 
-type ParseResult<'a, Output> = Result<(&'a str, Output), &'a str>;
+// generalized over the input type so the same combinators work over
+// `&str`, `&[u8]`, a slice of pre-lexed tokens, or any other stream type,
+// not just UTF-8 text
+
+type ParseResult<Input, Output, Error> = Result<(Input, Output), Error>;
+
+// replaces the bare `Err(input)` of the early combinators: keeps the byte
+// offset the failure happened at (computed against whatever input the
+// caller originally started with, since every failure slice in this crate
+// is a suffix of it), the set of things that would have made it succeed,
+// and an optional human label for the surrounding rule (set via `.label`)
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ParseError<'a> {
+    at: &'a str,
+    expected: Vec<String>,
+    label: Option<String>,
+}
+
+impl<'a> ParseError<'a> {
+    fn new(at: &'a str, expected: &str) -> Self {
+        ParseError {
+            at,
+            expected: vec![expected.to_string()],
+            label: None,
+        }
+    }
+
+    // byte offset into `original`; only meaningful when `original` is the
+    // same string `at` was sliced from
+    fn offset(&self, original: &'a str) -> usize {
+        original.len() - self.at.len()
+    }
+}
+
+// lets combinators like `pred` synthesize an error from just the input
+// they were given, without knowing anything about what a caller further
+// up actually wanted
+impl<'a> From<&'a str> for ParseError<'a> {
+    fn from(at: &'a str) -> Self {
+        ParseError::new(at, "value satisfying predicate")
+    }
+}
 
-trait Parser<'a, Output> {
-    fn parse(&self, input: &'a str) -> ParseResult<'a, Output>;
+// a composed parser's grammar, as data, so it can be rendered or inspected
+// instead of staying opaque behind a stack of combinator calls
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Bnf {
+    Literal(String),
+    Seq(Vec<Bnf>),
+    Alt(Vec<Bnf>),
+    Repeat0(Box<Bnf>),
+    Repeat1(Box<Bnf>),
+    Ref(String),
+}
+
+impl std::fmt::Display for Bnf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bnf::Literal(s) => write!(f, "\"{}\"", s),
+            Bnf::Seq(items) => {
+                let rendered: Vec<String> = items.iter().map(|b| b.to_string()).collect();
+                write!(f, "{}", rendered.join(" "))
+            }
+            Bnf::Alt(items) => {
+                let rendered: Vec<String> = items.iter().map(|b| b.to_string()).collect();
+                write!(f, "{}", rendered.join(" | "))
+            }
+            Bnf::Repeat0(inner) => write!(f, "{{ {} }}", inner),
+            Bnf::Repeat1(inner) => write!(f, "{} , {{ {} }}", inner, inner),
+            Bnf::Ref(name) => write!(f, "<{}>", name),
+        }
+    }
 }
 
-impl<'a, F, Output> Parser<'a, Output> for F
+trait Parser<Input, Output, Error> {
+    fn parse(&self, input: Input) -> ParseResult<Input, Output, Error>;
+
+    // `None` by default so ad-hoc closures don't have to describe
+    // themselves; the combinators below that have a fixed shape (literals,
+    // sequencing, repetition, alternation) override it
+    fn bnf(&self) -> Option<Bnf> {
+        None
+    }
+
+    // lets us write `match_literal("<").and_then(...)` instead of
+    // `and_then(match_literal("<"), ...)`, which reads a lot closer to how
+    // the grammar is actually shaped
+
+    fn map<'a, F, NewOutput>(self, map_fn: F) -> BoxedParser<'a, Input, NewOutput, Error>
+    where
+        Self: Sized + 'a,
+        Input: 'a,
+        Output: 'a,
+        Error: 'a,
+        NewOutput: 'a,
+        F: Fn(Output) -> NewOutput + 'a,
+    {
+        BoxedParser::new(map(self, map_fn))
+    }
+
+    fn pred<'a, F>(self, predicate: F) -> BoxedParser<'a, Input, Output, Error>
+    where
+        Self: Sized + 'a,
+        Input: Copy + 'a,
+        Output: 'a,
+        Error: From<Input> + 'a,
+        F: Fn(&Output) -> bool + 'a,
+    {
+        BoxedParser::new(pred(self, predicate))
+    }
+
+    // the important one: lets the parser we run next depend on the output
+    // of this one, which is what context-sensitive grammars (e.g. an XML
+    // closing tag that has to match the opening tag's name) need and the
+    // fixed combinators above can't give us
+
+    fn and_then<'a, F, NextParser, NewOutput>(self, f: F) -> BoxedParser<'a, Input, NewOutput, Error>
+    where
+        Self: Sized + 'a,
+        Input: 'a,
+        Output: 'a,
+        Error: 'a,
+        NewOutput: 'a,
+        NextParser: Parser<Input, NewOutput, Error> + 'a,
+        F: Fn(Output) -> NextParser + 'a,
+    {
+        BoxedParser::new(and_then(self, f))
+    }
+}
+
+impl<F, Input, Output, Error> Parser<Input, Output, Error> for F
 where
-    F: Fn(&'a str) -> ParseResult<Output>,
+    F: Fn(Input) -> ParseResult<Input, Output, Error>,
 {
-    fn parse(&self, input: &'a str) -> ParseResult<'a, Output> {
+    fn parse(&self, input: Input) -> ParseResult<Input, Output, Error> {
         self(input)
     }
 }
 
+// wraps up a `Parser` behind a `Box<dyn Parser>` so that `.map`, `.pred`,
+// and `.and_then` can return a concrete, nameable type instead of forcing
+// every caller to spell out the ever-growing `impl Parser<...>` type of a
+// deeply chained combinator
+
+struct BoxedParser<'a, Input, Output, Error> {
+    parser: Box<dyn Parser<Input, Output, Error> + 'a>,
+}
+
+impl<'a, Input, Output, Error> BoxedParser<'a, Input, Output, Error> {
+    fn new<P>(parser: P) -> Self
+    where
+        P: Parser<Input, Output, Error> + 'a,
+    {
+        BoxedParser {
+            parser: Box::new(parser),
+        }
+    }
+}
+
+impl<'a, Input, Output, Error> Parser<Input, Output, Error> for BoxedParser<'a, Input, Output, Error> {
+    fn parse(&self, input: Input) -> ParseResult<Input, Output, Error> {
+        self.parser.parse(input)
+    }
+
+    fn bnf(&self) -> Option<Bnf> {
+        self.parser.bnf()
+    }
+}
+
+// `.label` only makes sense once a parser's error is a `ParseError`, so it
+// lives on an extension trait rather than cluttering the core `Parser`
+// trait (which stays generic over any error type)
+
+// a labeled parser's grammar collapses to a named reference from the
+// caller's perspective, rather than expanding whatever `self.bnf()` would
+// otherwise render - the same way a BNF grammar refers to `<rule>` instead
+// of inlining its definition at every use site
+struct Label<P> {
+    parser: P,
+    label: &'static str,
+}
+
+impl<'a, Input, Output, P> Parser<Input, Output, ParseError<'a>> for Label<P>
+where
+    P: Parser<Input, Output, ParseError<'a>>,
+{
+    fn parse(&self, input: Input) -> ParseResult<Input, Output, ParseError<'a>> {
+        self.parser.parse(input).map_err(|mut err| {
+            err.expected = vec![self.label.to_string()];
+            err.label = Some(self.label.to_string());
+            err
+        })
+    }
+
+    fn bnf(&self) -> Option<Bnf> {
+        Some(Bnf::Ref(self.label.to_string()))
+    }
+}
+
+trait Labelable<'a, Input, Output>: Parser<Input, Output, ParseError<'a>> {
+    fn label(self, label: &'static str) -> BoxedParser<'a, Input, Output, ParseError<'a>>
+    where
+        Self: Sized + 'a,
+        Input: 'a,
+        Output: 'a,
+    {
+        BoxedParser::new(Label { parser: self, label })
+    }
+}
+
+impl<'a, Input, Output, P> Labelable<'a, Input, Output> for P where P: Parser<Input, Output, ParseError<'a>> {}
+
 // this is what match_literal("a") essentially returns
 
-fn the_letter_a(input: &str) -> ParseResult<()> {
+fn the_letter_a(input: &str) -> ParseResult<&str, (), &str> {
     match input.chars().next() {
         Some('a') => Ok((&input['a'.len_utf8()..], ())),
         _ => Err(input),
@@ -35,21 +236,35 @@ fn the_letter_a(input: &str) -> ParseResult<()> {
 //
 // ironically, the variability of `expected` makes length extraction nicer to look at!
 
-fn match_literal<'a>(expected: &'static str) -> impl Parser<'a, ()> {
-    move |input: &'a str| match input.get(0..expected.len()) {
-        Some(next) if next == expected => Ok((&input[expected.len()..], ())),
-        _ => Err(input),
+struct MatchLiteral {
+    expected: &'static str,
+}
+
+impl<'a> Parser<&'a str, (), ParseError<'a>> for MatchLiteral {
+    fn parse(&self, input: &'a str) -> ParseResult<&'a str, (), ParseError<'a>> {
+        match input.get(0..self.expected.len()) {
+            Some(next) if next == self.expected => Ok((&input[self.expected.len()..], ())),
+            _ => Err(ParseError::new(input, self.expected)),
+        }
+    }
+
+    fn bnf(&self) -> Option<Bnf> {
+        Some(Bnf::Literal(self.expected.to_string()))
     }
 }
 
+fn match_literal<'a>(expected: &'static str) -> impl Parser<&'a str, (), ParseError<'a>> {
+    MatchLiteral { expected }
+}
+
 // answer to Exercise 1
 //
 // see https://doc.rust-lang.org/std/primitive.str.html#method.strip_prefix
 
-fn match_literal_improved<'a>(expected: &'static str) -> impl Parser<'a, ()> {
+fn match_literal_improved<'a>(expected: &'static str) -> impl Parser<&'a str, (), ParseError<'a>> {
     move |input: &'a str| match input.strip_prefix(expected) {
         Some(next) => Ok((next, ())),
-        None => Err(input),
+        None => Err(ParseError::new(input, expected)),
     }
 }
 
@@ -61,9 +276,12 @@ fn literal_parser() {
         Ok(("kadabraalakazam", ())),
         parser.parse("abrakadabraalakazam")
     );
-    assert_eq!(Err(""), parser.parse(""));
-    assert_eq!(Err("abc"), parser.parse("abc"));
-    assert_eq!(Err("pikachu"), parser.parse("pikachu"));
+    assert_eq!(Err(ParseError::new("", "abra")), parser.parse(""));
+    assert_eq!(Err(ParseError::new("abc", "abra")), parser.parse("abc"));
+    assert_eq!(
+        Err(ParseError::new("pikachu", "abra")),
+        parser.parse("pikachu")
+    );
 }
 
 #[test]
@@ -74,20 +292,30 @@ fn literal_parser_improved() {
         Ok(("kadabraalakazam", ())),
         parser.parse("abrakadabraalakazam")
     );
-    assert_eq!(Err(""), parser.parse(""));
-    assert_eq!(Err("abc"), parser.parse("abc"));
-    assert_eq!(Err("pikachu"), parser.parse("pikachu"));
+    assert_eq!(Err(ParseError::new("", "abra")), parser.parse(""));
+    assert_eq!(Err(ParseError::new("abc", "abra")), parser.parse("abc"));
+    assert_eq!(
+        Err(ParseError::new("pikachu", "abra")),
+        parser.parse("pikachu")
+    );
+}
+
+#[test]
+fn literal_parser_reports_offset() {
+    let input = "abrakadabraalakazam";
+    let error = match_literal("nope").parse(input).unwrap_err();
+    assert_eq!(0, error.offset(input));
 }
 
 // matches the regex [a-zA-Z]([a-zA-Z0-9]|-)*
 
-fn identifier(input: &str) -> ParseResult<String> {
+fn identifier(input: &str) -> ParseResult<&str, String, ParseError<'_>> {
     let mut matched = String::new();
     let mut chars = input.chars();
 
     match chars.next() {
         Some(next) if next.is_alphabetic() => matched.push(next),
-        _ => return Err(input),
+        _ => return Err(ParseError::new(input, "identifier")),
     }
 
     while let Some(next) = chars.next() {
@@ -108,23 +336,46 @@ fn identifier(input: &str) -> ParseResult<String> {
 fn identifier_parser() {
     assert_eq!(Ok(("", "a-b-c-d".to_string())), identifier("a-b-c-d"));
     assert_eq!(Ok((" b-c-d", "a".to_string())), identifier("a b-c-d"));
-    assert_eq!(Err("!a-b-c-d"), identifier("!a-b-c-d"));
+    assert_eq!(
+        Err(ParseError::new("!a-b-c-d", "identifier")),
+        identifier("!a-b-c-d")
+    );
 }
 
 // given f and g, returns (f o g)
+//
+// input-agnostic: works the same over `&str`, `&[Token]`, or anything else
+// a `Parser` is implemented for
+
+struct Pair<P1, P2> {
+    parser1: P1,
+    parser2: P2,
+}
 
-fn pair<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, (R1, R2)>
+impl<Input, P1, P2, R1, R2, Error> Parser<Input, (R1, R2), Error> for Pair<P1, P2>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>,
+    P1: Parser<Input, R1, Error>,
+    P2: Parser<Input, R2, Error>,
 {
-    move |input| {
-        parser1.parse(input).and_then(|(next_input, result1)| {
-            parser2
+    fn parse(&self, input: Input) -> ParseResult<Input, (R1, R2), Error> {
+        self.parser1.parse(input).and_then(|(next_input, result1)| {
+            self.parser2
                 .parse(next_input)
                 .map(|(last_input, result2)| (last_input, (result1, result2)))
         })
     }
+
+    fn bnf(&self) -> Option<Bnf> {
+        Some(Bnf::Seq(vec![self.parser1.bnf()?, self.parser2.bnf()?]))
+    }
+}
+
+fn pair<Input, P1, P2, R1, R2, Error>(parser1: P1, parser2: P2) -> impl Parser<Input, (R1, R2), Error>
+where
+    P1: Parser<Input, R1, Error>,
+    P2: Parser<Input, R2, Error>,
+{
+    Pair { parser1, parser2 }
 }
 
 // ｶｯｺｲｲ
@@ -136,37 +387,69 @@ fn pair_combinator() {
         Ok(("/>", ((), "br".to_string()))),
         tag_opener.parse("<br/>")
     );
-    assert_eq!(Err("oh no"), tag_opener.parse("oh no"));
     assert_eq!(
-        Err("!-- I'm just a comment! -->"),
+        Err(ParseError::new("oh no", "<")),
+        tag_opener.parse("oh no")
+    );
+    assert_eq!(
+        Err(ParseError::new(
+            "!-- I'm just a comment! -->",
+            "identifier"
+        )),
         tag_opener.parse("<!-- I'm just a comment! -->")
     );
 }
 
-fn map<'a, P, F, A, B>(parser: P, map_fn: F) -> impl Parser<'a, B>
+struct Map<P, F, A> {
+    parser: P,
+    map_fn: F,
+    // `A` only shows up in the `impl`'s where-clause otherwise, which
+    // isn't enough for the compiler to consider it constrained
+    _input: std::marker::PhantomData<A>,
+}
+
+impl<Input, P, F, A, B, Error> Parser<Input, B, Error> for Map<P, F, A>
 where
-    P: Parser<'a, A>,
+    P: Parser<Input, A, Error>,
     F: Fn(A) -> B,
 {
-    move |input| {
-        parser
+    fn parse(&self, input: Input) -> ParseResult<Input, B, Error> {
+        self.parser
             .parse(input)
-            .map(|(next_input, result)| (next_input, map_fn(result)))
+            .map(|(next_input, result)| (next_input, (self.map_fn)(result)))
+    }
+
+    // mapping only transforms the output value, not the shape of what was
+    // consumed, so the grammar underneath is unchanged
+    fn bnf(&self) -> Option<Bnf> {
+        self.parser.bnf()
+    }
+}
+
+fn map<Input, P, F, A, B, Error>(parser: P, map_fn: F) -> impl Parser<Input, B, Error>
+where
+    P: Parser<Input, A, Error>,
+    F: Fn(A) -> B,
+{
+    Map {
+        parser,
+        map_fn,
+        _input: std::marker::PhantomData,
     }
 }
 
-fn left<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, R1>
+fn left<Input, P1, P2, R1, R2, Error>(parser1: P1, parser2: P2) -> impl Parser<Input, R1, Error>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>,
+    P1: Parser<Input, R1, Error>,
+    P2: Parser<Input, R2, Error>,
 {
     map(pair(parser1, parser2), |(left, _right)| left)
 }
 
-fn right<'a, P1, P2, R1, R2>(parser1: P1, parser2: P2) -> impl Parser<'a, R2>
+fn right<Input, P1, P2, R1, R2, Error>(parser1: P1, parser2: P2) -> impl Parser<Input, R2, Error>
 where
-    P1: Parser<'a, R1>,
-    P2: Parser<'a, R2>,
+    P1: Parser<Input, R1, Error>,
+    P2: Parser<Input, R2, Error>,
 {
     map(pair(parser1, parser2), |(_left, right)| right)
 }
@@ -175,50 +458,98 @@ where
 fn right_combinator() {
     let tag_opener = right(match_literal("<"), identifier);
     assert_eq!(Ok(("/>", "br".to_string())), tag_opener.parse("<br/>"));
-    assert_eq!(Err("oh no"), tag_opener.parse("oh no"));
     assert_eq!(
-        Err("!-- I'm just a comment! -->"),
+        Err(ParseError::new("oh no", "<")),
+        tag_opener.parse("oh no")
+    );
+    assert_eq!(
+        Err(ParseError::new(
+            "!-- I'm just a comment! -->",
+            "identifier"
+        )),
         tag_opener.parse("<!-- I'm just a comment! -->")
     );
 }
 
-fn one_or_more<'a, P, A>(parser: P) -> impl Parser<'a, Vec<A>>
+// `Input: Copy` lets us rewind to `input` after a failed attempt without
+// the parser taking ownership of the stream out from under us; `&str` and
+// `&[T]` are both `Copy`, so lexer tokens slot in just as well as text
+
+struct OneOrMore<P> {
+    parser: P,
+}
+
+impl<Input, P, A, Error> Parser<Input, Vec<A>, Error> for OneOrMore<P>
 where
-    P: Parser<'a, A>,
+    Input: Copy,
+    P: Parser<Input, A, Error>,
 {
-    move |mut input| {
+    fn parse(&self, mut input: Input) -> ParseResult<Input, Vec<A>, Error> {
         let mut result = Vec::new();
 
-        if let Ok((next_input, first_item)) = parser.parse(input) {
-            input = next_input;
-            result.push(first_item);
-        } else {
-            return Err(input);
+        // the first attempt's failure is the real reason this repetition
+        // didn't match, so it's returned as-is instead of being replaced
+        // by a synthesized one the way `pred` has to
+        match self.parser.parse(input) {
+            Ok((next_input, first_item)) => {
+                input = next_input;
+                result.push(first_item);
+            }
+            Err(err) => return Err(err),
         }
 
-        while let Ok((next_input, next_item)) = parser.parse(input) {
+        while let Ok((next_input, next_item)) = self.parser.parse(input) {
             input = next_input;
             result.push(next_item);
         }
 
         Ok((input, result))
     }
+
+    fn bnf(&self) -> Option<Bnf> {
+        Some(Bnf::Repeat1(Box::new(self.parser.bnf()?)))
+    }
 }
 
-fn zero_or_more<'a, P, A>(parser: P) -> impl Parser<'a, Vec<A>>
+fn one_or_more<Input, P, A, Error>(parser: P) -> impl Parser<Input, Vec<A>, Error>
 where
-    P: Parser<'a, A>,
+    Input: Copy,
+    P: Parser<Input, A, Error>,
 {
-    move |mut input| {
+    OneOrMore { parser }
+}
+
+struct ZeroOrMore<P> {
+    parser: P,
+}
+
+impl<Input, P, A, Error> Parser<Input, Vec<A>, Error> for ZeroOrMore<P>
+where
+    Input: Copy,
+    P: Parser<Input, A, Error>,
+{
+    fn parse(&self, mut input: Input) -> ParseResult<Input, Vec<A>, Error> {
         let mut result = Vec::new();
 
-        while let Ok((next_input, next_item)) = parser.parse(input) {
+        while let Ok((next_input, next_item)) = self.parser.parse(input) {
             input = next_input;
             result.push(next_item);
         }
 
         Ok((input, result))
     }
+
+    fn bnf(&self) -> Option<Bnf> {
+        Some(Bnf::Repeat0(Box::new(self.parser.bnf()?)))
+    }
+}
+
+fn zero_or_more<Input, P, A, Error>(parser: P) -> impl Parser<Input, Vec<A>, Error>
+where
+    Input: Copy,
+    P: Parser<Input, A, Error>,
+{
+    ZeroOrMore { parser }
 }
 
 #[test]
@@ -226,10 +557,10 @@ fn one_or_more_combinator() {
     let parser = one_or_more(match_literal("le"));
     assert_eq!(Ok(("", vec![(), (), ()])), parser.parse("lelele"));
     assert_eq!(
-        Err("delelelelelewhooop"),
+        Err(ParseError::new("delelelelelewhooop", "le")),
         parser.parse("delelelelelewhooop")
     );
-    assert_eq!(Err(""), parser.parse(""));
+    assert_eq!(Err(ParseError::new("", "le")), parser.parse(""));
 }
 
 #[test]
@@ -243,16 +574,18 @@ fn zero_or_more_combinator() {
     assert_eq!(Ok(("", vec![])), parser.parse(""));
 }
 
-fn any_char(input: &str) -> ParseResult<char> {
+fn any_char(input: &str) -> ParseResult<&str, char, ParseError<'_>> {
     match input.chars().next() {
         Some(next) => Ok((&input[next.len_utf8()..], next)),
-        _ => Err(input),
+        _ => Err(ParseError::new(input, "any character")),
     }
 }
 
-fn pred<'a, P, A, F>(parser: P, predicate: F) -> impl Parser<'a, A>
+fn pred<Input, P, A, F, Error>(parser: P, predicate: F) -> impl Parser<Input, A, Error>
 where
-    P: Parser<'a, A>,
+    Input: Copy,
+    Error: From<Input>,
+    P: Parser<Input, A, Error>,
     F: Fn(&A) -> bool,
 {
     move |input| {
@@ -261,7 +594,7 @@ where
                 return Ok((next_input, value));
             }
         }
-        Err(input)
+        Err(Error::from(input))
     }
 }
 
@@ -269,22 +602,128 @@ where
 fn predicate_combinator() {
     let parser = pred(any_char, |c| *c == 'o');
     assert_eq!(Ok(("ctazooka", 'o')), parser.parse("octazooka"));
-    assert_eq!(Err("bazooka"), parser.parse("bazooka"));
+    assert_eq!(
+        Err(ParseError::new("bazooka", "value satisfying predicate")),
+        parser.parse("bazooka")
+    );
+}
+
+// given parser and f, runs parser and hands its output to f, then runs
+// whatever parser f returns against the remaining input
+
+fn and_then<Input, P, F, A, B, NextP, Error>(parser: P, f: F) -> impl Parser<Input, B, Error>
+where
+    P: Parser<Input, A, Error>,
+    NextP: Parser<Input, B, Error>,
+    F: Fn(A) -> NextP,
+{
+    move |input| parser.parse(input).and_then(|(next_input, result)| f(result).parse(next_input))
 }
 
-fn whitespace_char<'a>() -> impl Parser<'a, char> {
+// built from whatever identifier and_then hands us, so it needs its own
+// named function rather than a closure: a closure's parameter lifetime is
+// chosen independently of the `Input` and_then already committed to, and
+// the two never unify, while a free fn with an explicit lifetime (same
+// trick as `match_literal_improved` above) ties them together directly
+fn closing_tag<'a>(open_tag: String) -> impl Parser<&'a str, (), ParseError<'a>> {
+    let expected = format!("</{}>", open_tag);
+    move |input: &'a str| match input.strip_prefix(expected.as_str()) {
+        Some(next) => Ok((next, ())),
+        None => Err(ParseError::new(input, "matching closing tag")),
+    }
+}
+
+#[test]
+fn and_then_combinator() {
+    // a closing tag has to match whatever name the opening tag used, which
+    // and_then lets us express directly: parse the opening identifier, then
+    // build the next parser (a literal match on that exact name) from it
+    let parser = identifier.and_then(closing_tag);
+
+    assert_eq!(Ok(("", ())), parser.parse("div</div>"));
+    assert_eq!(
+        Err(ParseError::new("</span>", "matching closing tag")),
+        parser.parse("div</span>")
+    );
+}
+
+// tries each parser in turn and returns the first success, or the last
+// failure if none of them matched
+
+struct Alt<P> {
+    parsers: Vec<P>,
+}
+
+impl<Input, P, A, Error> Parser<Input, A, Error> for Alt<P>
+where
+    Input: Copy,
+    P: Parser<Input, A, Error>,
+{
+    fn parse(&self, input: Input) -> ParseResult<Input, A, Error> {
+        let mut parsers = self.parsers.iter();
+        let first = parsers.next().expect("alt requires at least one parser");
+        let mut last_result = first.parse(input);
+
+        for parser in parsers {
+            if last_result.is_ok() {
+                break;
+            }
+            last_result = parser.parse(input);
+        }
+
+        last_result
+    }
+
+    fn bnf(&self) -> Option<Bnf> {
+        let branches: Option<Vec<Bnf>> = self.parsers.iter().map(|p| p.bnf()).collect();
+        branches.map(Bnf::Alt)
+    }
+}
+
+fn alt<Input, P, A, Error>(parsers: Vec<P>) -> impl Parser<Input, A, Error>
+where
+    Input: Copy,
+    P: Parser<Input, A, Error>,
+{
+    Alt { parsers }
+}
+
+#[test]
+fn alt_combinator() {
+    let parser = alt(vec![match_literal("cat"), match_literal("dog")]);
+    assert_eq!(Ok(("s", ())), parser.parse("cats"));
+    assert_eq!(Ok(("s", ())), parser.parse("dogs"));
+    assert_eq!(Err(ParseError::new("fish", "dog")), parser.parse("fish"));
+}
+
+#[test]
+fn bnf_describes_a_composed_grammar() {
+    let parser = pair(match_literal("<"), one_or_more(match_literal("a")));
+    assert_eq!(
+        "\"<\" \"a\" , { \"a\" }",
+        parser.bnf().unwrap().to_string()
+    );
+
+    let alternatives = alt(vec![match_literal("cat"), match_literal("dog")]);
+    assert_eq!("\"cat\" | \"dog\"", alternatives.bnf().unwrap().to_string());
+
+    let labeled = quoted_string().label("attribute value");
+    assert_eq!("<attribute value>", labeled.bnf().unwrap().to_string());
+}
+
+fn whitespace_char<'a>() -> impl Parser<&'a str, char, ParseError<'a>> {
     pred(any_char, |c| c.is_whitespace())
 }
 
-fn space1<'a>() -> impl Parser<'a, Vec<char>> {
+fn space1<'a>() -> impl Parser<&'a str, Vec<char>, ParseError<'a>> {
     one_or_more(whitespace_char())
 }
 
-fn space0<'a>() -> impl Parser<'a, Vec<char>> {
+fn space0<'a>() -> impl Parser<&'a str, Vec<char>, ParseError<'a>> {
     zero_or_more(whitespace_char())
 }
 
-fn quoted_string<'a>() -> impl Parser<'a, String> {
+fn quoted_string<'a>() -> impl Parser<&'a str, String, ParseError<'a>> {
     map(
         right(
             match_literal("\""),
@@ -304,3 +743,16 @@ fn quoted_string_parser() {
         quoted_string().parse("\"value\"")
     );
 }
+
+#[test]
+fn label_combinator() {
+    let parser = quoted_string().label("attribute value");
+    assert_eq!(
+        Err(ParseError {
+            at: "value\"",
+            expected: vec!["attribute value".to_string()],
+            label: Some("attribute value".to_string()),
+        }),
+        parser.parse("value\"")
+    );
+}
@@ -0,0 +1,334 @@
+// a second, lossless parsing mode that runs alongside the typed `Element`
+// path: instead of building a value directly, parsing pushes `Event`s
+// into a flat sink, and a separate pass assembles them into a
+// `SyntaxTree` whose leaves cover every byte of the input - including
+// whitespace - which editor-style consumers (formatting, incremental
+// reparsing) need and the typed path was never meant to give them.
+//
+// a bad attribute doesn't abort the surrounding element the way it would
+// on the typed path: it becomes an `Error` event, and parsing skips
+// forward to a caller-provided recovery set instead of returning `Err`,
+// so the rest of the element is still recovered.
+//
+// scoped to self-closing elements (`<tag attr="value" ... />`) rather
+// than the full nested grammar `Element` describes - the typed path
+// never grew a parser for that grammar either, so there's nothing wider
+// to stay lossless with yet.
+
+use crate::{identifier, match_literal, quoted_string, space0, space1, ParseError, Parser};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SyntaxKind {
+    Element,
+    TagName,
+    AttributeName,
+    AttributeValue,
+    Whitespace,
+    Punctuation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Event {
+    StartNode(SyntaxKind),
+    Token(std::ops::Range<usize>),
+    Error(String),
+    FinishNode,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SyntaxTree {
+    kind: SyntaxKind,
+    children: Vec<SyntaxNode>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SyntaxNode {
+    Node(SyntaxTree),
+    Token(std::ops::Range<usize>),
+    Error(String),
+}
+
+// assembles a flat event stream into a tree: one stack frame per
+// currently-open `StartNode`, popped and attached to its parent on the
+// matching `FinishNode`
+fn build_tree(events: Vec<Event>) -> SyntaxTree {
+    let mut stack: Vec<(SyntaxKind, Vec<SyntaxNode>)> = Vec::new();
+    let mut root = None;
+
+    for event in events {
+        match event {
+            Event::StartNode(kind) => stack.push((kind, Vec::new())),
+            Event::Token(range) => {
+                stack
+                    .last_mut()
+                    .expect("token outside any node")
+                    .1
+                    .push(SyntaxNode::Token(range));
+            }
+            Event::Error(message) => {
+                stack
+                    .last_mut()
+                    .expect("error outside any node")
+                    .1
+                    .push(SyntaxNode::Error(message));
+            }
+            Event::FinishNode => {
+                let (kind, children) = stack.pop().expect("FinishNode without a matching StartNode");
+                let node = SyntaxTree { kind, children };
+                match stack.last_mut() {
+                    Some((_, parent_children)) => parent_children.push(SyntaxNode::Node(node)),
+                    None => root = Some(node),
+                }
+            }
+        }
+    }
+
+    root.expect("event stream did not finish its root node")
+}
+
+// the byte range in `original` spanned by consuming from `before` down to
+// `after` - both are always suffixes of `original`, same as `ParseError::offset`
+fn span(original: &str, before: &str, after: &str) -> std::ops::Range<usize> {
+    let start = original.len() - before.len();
+    let end = original.len() - after.len();
+    start..end
+}
+
+fn emit_token(events: &mut Vec<Event>, original: &str, before: &str, after: &str, kind: SyntaxKind) {
+    events.push(Event::StartNode(kind));
+    events.push(Event::Token(span(original, before, after)));
+    events.push(Event::FinishNode);
+}
+
+fn skip_to_recovery<'a>(input: &'a str, recovery: &[char]) -> &'a str {
+    match input.find(|c| recovery.contains(&c)) {
+        Some(index) => &input[index..],
+        None => "",
+    }
+}
+
+fn parse_attribute<'a>(
+    input: &'a str,
+    original: &'a str,
+    events: &mut Vec<Event>,
+) -> Result<&'a str, ParseError<'a>> {
+    let (rest, _name) = identifier(input)?;
+    emit_token(events, original, input, rest, SyntaxKind::AttributeName);
+
+    let eq_start = rest;
+    let (rest, _) = match_literal("=").parse(rest)?;
+    emit_token(events, original, eq_start, rest, SyntaxKind::Punctuation);
+
+    let value_start = rest;
+    let (rest, _value) = quoted_string().parse(rest)?;
+    emit_token(events, original, value_start, rest, SyntaxKind::AttributeValue);
+
+    Ok(rest)
+}
+
+// zero or more `space1 attribute` pairs; a failed attribute is recorded
+// as an `Error` event plus an entry in `errors`, and parsing resumes at
+// the first char in `recovery` rather than giving up on the element.
+//
+// the returned bool is true when a failed attribute couldn't find any
+// recovery point and ran off the end of the input - the caller already
+// has an error recorded for that and shouldn't report a second one for
+// the closing delimiter it will now also fail to find
+fn parse_attributes<'a>(
+    mut input: &'a str,
+    original: &'a str,
+    events: &mut Vec<Event>,
+    errors: &mut Vec<ParseError<'a>>,
+    recovery: &[char],
+) -> (&'a str, bool) {
+    loop {
+        let before_space = input;
+        let after_space = match space1().parse(input) {
+            Ok((rest, _)) => rest,
+            Err(_) => break,
+        };
+        if after_space.starts_with("/>") {
+            // trailing whitespace before the closing delimiter, not the
+            // start of another attribute - leave it for the caller
+            break;
+        }
+        emit_token(events, original, before_space, after_space, SyntaxKind::Whitespace);
+
+        let checkpoint = events.len();
+        match parse_attribute(after_space, original, events) {
+            Ok(rest) => input = rest,
+            Err(err) => {
+                events.truncate(checkpoint);
+                events.push(Event::Error(format!("expected {}", err.expected.join(" or "))));
+
+                let skipped_to = skip_to_recovery(err.at, recovery);
+                if skipped_to != after_space {
+                    events.push(Event::Token(span(original, after_space, skipped_to)));
+                }
+
+                errors.push(err);
+                input = skipped_to;
+
+                if skipped_to.is_empty() {
+                    return (input, true);
+                }
+            }
+        }
+    }
+    (input, false)
+}
+
+// closes out the element we were in the middle of on an unrecoverable
+// failure (a missing `<`, tag name, or closing `/>`): records the error
+// as both an event and a `ParseError`, folds whatever was left of the
+// input into a trailing token so no byte goes unaccounted for, and
+// returns the empty string since there's nothing left worth resuming at
+fn finish_with_error<'a>(
+    events: &mut Vec<Event>,
+    errors: &mut Vec<ParseError<'a>>,
+    original: &'a str,
+    at: &'a str,
+    err: ParseError<'a>,
+) -> &'a str {
+    events.push(Event::Error(format!("expected {}", err.expected.join(" or "))));
+    if !at.is_empty() {
+        events.push(Event::Token(span(original, at, "")));
+    }
+    errors.push(err);
+    events.push(Event::FinishNode);
+    ""
+}
+
+fn parse_element<'a>(
+    input: &'a str,
+    original: &'a str,
+    events: &mut Vec<Event>,
+    errors: &mut Vec<ParseError<'a>>,
+    recovery: &[char],
+) -> &'a str {
+    events.push(Event::StartNode(SyntaxKind::Element));
+
+    let rest = match match_literal("<").parse(input) {
+        Ok((rest, _)) => {
+            emit_token(events, original, input, rest, SyntaxKind::Punctuation);
+            rest
+        }
+        Err(err) => return finish_with_error(events, errors, original, input, err),
+    };
+
+    let rest = match identifier(rest) {
+        Ok((after_name, _name)) => {
+            emit_token(events, original, rest, after_name, SyntaxKind::TagName);
+            after_name
+        }
+        Err(err) => return finish_with_error(events, errors, original, rest, err),
+    };
+
+    let (rest, exhausted) = parse_attributes(rest, original, events, errors, recovery);
+    if exhausted {
+        // an attribute already reported an error it couldn't recover
+        // from before running out of input - the closing delimiter we'd
+        // also fail to find here isn't a second, distinct problem
+        events.push(Event::FinishNode);
+        return rest;
+    }
+
+    let before_trailing_space = rest;
+    let rest = match space0().parse(rest) {
+        Ok((after_space, spaces)) => {
+            if !spaces.is_empty() {
+                emit_token(events, original, before_trailing_space, after_space, SyntaxKind::Whitespace);
+            }
+            after_space
+        }
+        Err(_) => rest,
+    };
+
+    match match_literal("/>").parse(rest) {
+        Ok((after_close, _)) => {
+            emit_token(events, original, rest, after_close, SyntaxKind::Punctuation);
+            events.push(Event::FinishNode);
+            after_close
+        }
+        Err(err) => finish_with_error(events, errors, original, rest, err),
+    }
+}
+
+// a parallel entry point alongside the typed `Element` path: parses one
+// self-closing element and returns every byte as a lossless tree plus any
+// recovered errors instead of a value or a single failure
+fn parse_to_tree<'a>(input: &'a str, recovery: &[char]) -> (SyntaxTree, Vec<ParseError<'a>>) {
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    parse_element(input, input, &mut events, &mut errors, recovery);
+    (build_tree(events), errors)
+}
+
+#[cfg(test)]
+fn reconstruct(original: &str, tree: &SyntaxTree) -> String {
+    fn collect(original: &str, node: &SyntaxNode, out: &mut String) {
+        match node {
+            SyntaxNode::Node(tree) => {
+                for child in &tree.children {
+                    collect(original, child, out);
+                }
+            }
+            SyntaxNode::Token(range) => out.push_str(&original[range.clone()]),
+            SyntaxNode::Error(_) => {}
+        }
+    }
+
+    let mut out = String::new();
+    for child in &tree.children {
+        collect(original, child, &mut out);
+    }
+    out
+}
+
+#[test]
+fn lossless_tree_covers_a_well_formed_element() {
+    let input = "<br enabled=\"true\" />";
+    let (tree, errors) = parse_to_tree(input, &[' ', '/', '>']);
+    assert!(errors.is_empty());
+    assert_eq!(SyntaxKind::Element, tree.kind);
+    assert_eq!(input, reconstruct(input, &tree));
+}
+
+#[test]
+fn lossless_tree_recovers_from_a_malformed_attribute() {
+    let input = "<input !!! enabled=\"true\" />";
+    let (tree, errors) = parse_to_tree(input, &[' ', '/', '>']);
+    assert_eq!(1, errors.len());
+    assert_eq!(input, reconstruct(input, &tree));
+}
+
+#[test]
+fn lossless_tree_reports_one_error_for_a_truncated_attribute() {
+    // the unterminated quoted value runs off the end of the input, so
+    // there's no closing `/>` either - that's one problem, not two
+    let input = "<input enabled=\"true";
+    let (tree, errors) = parse_to_tree(input, &[' ', '/', '>']);
+    assert_eq!(1, errors.len());
+    assert_eq!(input, reconstruct(input, &tree));
+}
+
+#[test]
+fn lossless_tree_reports_one_error_for_an_unterminated_value_containing_recovery_chars() {
+    // the unterminated value's body contains a space and a `/`, both
+    // recovery chars - recovery has to start searching from the actual
+    // failure point (`err.at`, inside the quotes) rather than from where
+    // the attribute started, or it'll find one of those chars first and
+    // misparse the string's own tail as a second broken attribute
+    let input = "<input enabled=\"true false />";
+    let (tree, errors) = parse_to_tree(input, &[' ', '/', '>']);
+    assert_eq!(1, errors.len());
+    assert_eq!(input, reconstruct(input, &tree));
+}
+
+#[test]
+fn lossless_tree_reports_a_missing_tag_name() {
+    let input = "< />";
+    let (tree, errors) = parse_to_tree(input, &[' ', '/', '>']);
+    assert_eq!(1, errors.len());
+    assert_eq!(input, reconstruct(input, &tree));
+}